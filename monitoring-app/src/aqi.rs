@@ -0,0 +1,101 @@
+/// A US EPA breakpoint band: a pollutant concentration range mapped to an
+/// AQI index range.
+#[derive(Debug, Clone, Copy)]
+struct Breakpoint {
+    conc_lo: f64,
+    conc_hi: f64,
+    aqi_lo: u32,
+    aqi_hi: u32,
+}
+
+const PM2_5_BREAKPOINTS: &[Breakpoint] = &[
+    Breakpoint { conc_lo: 0.0, conc_hi: 12.0, aqi_lo: 0, aqi_hi: 50 },
+    Breakpoint { conc_lo: 12.1, conc_hi: 35.4, aqi_lo: 51, aqi_hi: 100 },
+    Breakpoint { conc_lo: 35.5, conc_hi: 55.4, aqi_lo: 101, aqi_hi: 150 },
+    Breakpoint { conc_lo: 55.5, conc_hi: 150.4, aqi_lo: 151, aqi_hi: 200 },
+    Breakpoint { conc_lo: 150.5, conc_hi: 250.4, aqi_lo: 201, aqi_hi: 300 },
+    Breakpoint { conc_lo: 250.5, conc_hi: 500.4, aqi_lo: 301, aqi_hi: 500 },
+];
+
+const PM10_BREAKPOINTS: &[Breakpoint] = &[
+    Breakpoint { conc_lo: 0.0, conc_hi: 54.0, aqi_lo: 0, aqi_hi: 50 },
+    Breakpoint { conc_lo: 55.0, conc_hi: 154.0, aqi_lo: 51, aqi_hi: 100 },
+    Breakpoint { conc_lo: 155.0, conc_hi: 254.0, aqi_lo: 101, aqi_hi: 150 },
+    Breakpoint { conc_lo: 255.0, conc_hi: 354.0, aqi_lo: 151, aqi_hi: 200 },
+    Breakpoint { conc_lo: 355.0, conc_hi: 424.0, aqi_lo: 201, aqi_hi: 300 },
+    Breakpoint { conc_lo: 425.0, conc_hi: 604.0, aqi_lo: 301, aqi_hi: 500 },
+];
+
+/// Piecewise-linear interpolation of a pollutant concentration into its AQI
+/// sub-index: find the breakpoint band `[C_lo, C_hi] -> [I_lo, I_hi]`
+/// containing `concentration` and interpolate. Concentrations above the top
+/// band clamp to the maximum AQI of 500.
+fn sub_index(concentration: f64, breakpoints: &[Breakpoint]) -> u32 {
+    let top = breakpoints.last().expect("breakpoints is never empty");
+    if concentration >= top.conc_hi {
+        return 500;
+    }
+
+    let band = breakpoints
+        .iter()
+        .find(|b| concentration <= b.conc_hi)
+        .unwrap_or(top);
+
+    let aqi = (band.aqi_hi - band.aqi_lo) as f64 / (band.conc_hi - band.conc_lo)
+        * (concentration - band.conc_lo)
+        + band.aqi_lo as f64;
+
+    aqi.round() as u32
+}
+
+/// Maps an AQI value to its EPA category label.
+pub fn category(aqi: u32) -> &'static str {
+    match aqi {
+        0..=50 => "Good",
+        51..=100 => "Moderate",
+        101..=150 => "Unhealthy for Sensitive Groups",
+        151..=200 => "Unhealthy",
+        201..=300 => "Very Unhealthy",
+        _ => "Hazardous",
+    }
+}
+
+/// Computes the overall AQI from PM2.5 and PM10 averages (in µg/m³), which
+/// is the maximum of the two pollutants' sub-indices.
+pub fn from_pm(pm2_5: f64, pm10: f64) -> u32 {
+    sub_index(pm2_5, PM2_5_BREAKPOINTS).max(sub_index(pm10, PM10_BREAKPOINTS))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_known_pm2_5_points() {
+        assert_eq!(sub_index(0.0, PM2_5_BREAKPOINTS), 0);
+        assert_eq!(sub_index(12.0, PM2_5_BREAKPOINTS), 50);
+        assert_eq!(sub_index(35.4, PM2_5_BREAKPOINTS), 100);
+    }
+
+    #[test]
+    fn clamps_above_top_band() {
+        assert_eq!(sub_index(1000.0, PM2_5_BREAKPOINTS), 500);
+        assert_eq!(sub_index(1000.0, PM10_BREAKPOINTS), 500);
+    }
+
+    #[test]
+    fn overall_is_the_worse_pollutant() {
+        // pm2_5 = 12 -> 50, pm10 = 154 -> 100
+        assert_eq!(from_pm(12.0, 154.0), 100);
+    }
+
+    #[test]
+    fn categories_match_epa_labels() {
+        assert_eq!(category(0), "Good");
+        assert_eq!(category(75), "Moderate");
+        assert_eq!(category(125), "Unhealthy for Sensitive Groups");
+        assert_eq!(category(175), "Unhealthy");
+        assert_eq!(category(250), "Very Unhealthy");
+        assert_eq!(category(400), "Hazardous");
+    }
+}
@@ -0,0 +1,95 @@
+use std::time::Duration;
+
+/// Remote commands accepted on the `<topic>/cmd/#` tree, so the monitor can
+/// be reconfigured at runtime instead of requiring a restart.
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    SetInterval(Duration),
+    SetMeasurements(usize),
+    Sample,
+    Wake,
+    Sleep,
+}
+
+/// Parses a command from the topic suffix after `<topic>/cmd/` and its
+/// payload. Unknown suffixes, unparsable payloads and out-of-range values
+/// (a zero or negative interval, zero measurements) are ignored, since the
+/// `cmd/#` tree has no authentication and a bad value would otherwise panic
+/// every reader loop that picks it up.
+pub fn parse(topic_suffix: &str, payload: &str) -> Option<Command> {
+    match topic_suffix {
+        "interval" => payload
+            .trim()
+            .parse()
+            .ok()
+            .filter(|secs: &u64| *secs > 0)
+            .map(|secs| Command::SetInterval(Duration::from_secs(secs))),
+        "measurements" => payload
+            .trim()
+            .parse()
+            .ok()
+            .filter(|measurements: &usize| *measurements > 0)
+            .map(Command::SetMeasurements),
+        "sample" => Some(Command::Sample),
+        "wake" => Some(Command::Wake),
+        "sleep" => Some(Command::Sleep),
+        _ => None,
+    }
+}
+
+/// Runtime-adjustable parameters of the sampling loop, shared between the
+/// command listener and the main loop.
+pub struct Controls {
+    pub interval: Duration,
+    pub measurements: usize,
+    pub wake: bool,
+    pub sleep: bool,
+}
+
+impl Controls {
+    pub fn new(interval: Duration, measurements: usize) -> Self {
+        Controls {
+            interval,
+            measurements,
+            wake: false,
+            sleep: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_interval() {
+        assert_eq!(
+            parse("interval", "30"),
+            Some(Command::SetInterval(Duration::from_secs(30)))
+        );
+    }
+
+    #[test]
+    fn parses_measurements() {
+        assert_eq!(parse("measurements", "5"), Some(Command::SetMeasurements(5)));
+    }
+
+    #[test]
+    fn parses_trigger_commands() {
+        assert_eq!(parse("sample", ""), Some(Command::Sample));
+        assert_eq!(parse("wake", ""), Some(Command::Wake));
+        assert_eq!(parse("sleep", ""), Some(Command::Sleep));
+    }
+
+    #[test]
+    fn ignores_unknown_topics_and_payloads() {
+        assert_eq!(parse("unknown", "1"), None);
+        assert_eq!(parse("interval", "not-a-number"), None);
+    }
+
+    #[test]
+    fn rejects_zero_interval_and_measurements() {
+        assert_eq!(parse("interval", "0"), None);
+        assert_eq!(parse("measurements", "0"), None);
+    }
+}
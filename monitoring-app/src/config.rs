@@ -0,0 +1,158 @@
+use serde_derive::Deserialize;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// A declarative description of every sensor and metric the monitor should poll.
+///
+/// Loaded from a TOML or JSON file passed via `--config`. Without a config
+/// file, the `--device`/`--interval` flags still drive a simple
+/// single-sensor setup; with one, those flags are not used.
+#[derive(Debug, Deserialize)]
+pub struct Config {
+    pub sensors: Vec<SensorConfig>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SensorConfig {
+    Pms7003 {
+        /// Path to the pms-7003 serial device
+        device: PathBuf,
+        metrics: Vec<MetricConfig>,
+    },
+    Bme280 {
+        /// Path to the I2C bus the sensor is attached to
+        bus: PathBuf,
+        #[serde(default)]
+        address: Bme280Address,
+        metrics: Vec<MetricConfig>,
+    },
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Bme280Address {
+    Primary,
+    Secondary,
+}
+
+impl Default for Bme280Address {
+    fn default() -> Self {
+        Bme280Address::Primary
+    }
+}
+
+/// A single published value: how often to sample it, an optional scale
+/// multiplier and an optional topic suffix override.
+#[derive(Debug, Deserialize)]
+pub struct MetricConfig {
+    pub name: String,
+    #[serde(deserialize_with = "deserialize_period")]
+    pub period: Duration,
+    #[serde(default = "default_scale")]
+    pub scale: f64,
+    pub topic: Option<String>,
+}
+
+fn default_scale() -> f64 {
+    1.0
+}
+
+fn deserialize_period<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let raw = String::deserialize(deserializer)?;
+    parse_period(&raw).map_err(serde::de::Error::custom)
+}
+
+/// Parses periods like `"3s"`, `"500ms"` or `"1m"` into a [`Duration`].
+pub fn parse_period(raw: &str) -> Result<Duration, String> {
+    let raw = raw.trim();
+    let split_at = raw
+        .find(|c: char| !c.is_ascii_digit())
+        .ok_or_else(|| format!("missing time unit in period '{}'", raw))?;
+    let (value, unit) = raw.split_at(split_at);
+    let value: u64 = value
+        .parse()
+        .map_err(|_| format!("invalid period '{}'", raw))?;
+
+    if value == 0 {
+        return Err(format!("period '{}' must be greater than zero", raw));
+    }
+
+    match unit {
+        "ms" => Ok(Duration::from_millis(value)),
+        "s" => Ok(Duration::from_secs(value)),
+        "m" => Ok(Duration::from_secs(value * 60)),
+        "h" => Ok(Duration::from_secs(value * 60 * 60)),
+        other => Err(format!("unknown period unit '{}' in '{}'", other, raw)),
+    }
+}
+
+#[derive(Debug)]
+pub enum LoadError {
+    Read(std::io::Error),
+    Parse(String),
+}
+
+impl std::fmt::Display for LoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoadError::Read(e) => write!(f, "could not read config file: {}", e),
+            LoadError::Parse(e) => write!(f, "could not parse config file: {}", e),
+        }
+    }
+}
+
+impl Config {
+    /// Loads a config from `path`, guessing the format (TOML or JSON) from
+    /// the file extension and defaulting to TOML.
+    pub fn load(path: &Path) -> Result<Config, LoadError> {
+        let contents = std::fs::read_to_string(path).map_err(LoadError::Read)?;
+
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => serde_json::from_str(&contents).map_err(|e| LoadError::Parse(e.to_string())),
+            _ => toml::from_str(&contents).map_err(|e| LoadError::Parse(e.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_periods() {
+        assert_eq!(parse_period("3s").unwrap(), Duration::from_secs(3));
+        assert_eq!(parse_period("1m").unwrap(), Duration::from_secs(60));
+        assert_eq!(parse_period("500ms").unwrap(), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn rejects_unknown_units() {
+        assert!(parse_period("3x").is_err());
+    }
+
+    #[test]
+    fn rejects_zero_periods() {
+        assert!(parse_period("0s").is_err());
+        assert!(parse_period("0m").is_err());
+    }
+
+    #[test]
+    fn loads_toml_config() {
+        let toml = r#"
+            [[sensors]]
+            type = "pms7003"
+            device = "/dev/ttyS0"
+
+            [[sensors.metrics]]
+            name = "pm2_5"
+            period = "3s"
+            scale = 1.0
+        "#;
+        let config: Config = toml::from_str(toml).unwrap();
+        assert_eq!(config.sensors.len(), 1);
+    }
+}
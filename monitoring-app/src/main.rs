@@ -1,8 +1,19 @@
+mod aqi;
+mod commands;
+mod config;
+mod measurement;
+mod mqtt;
+mod simulate;
+
+use commands::{Command, Controls};
+use config::{Bme280Address, Config, MetricConfig, SensorConfig};
 use linux_embedded_hal::Serial;
+use measurement::{AqiReading, EnvironmentReadings, Measurement, PmReadings};
+use mqtt::Handle as MqttHandle;
 use pms_7003::{OutputFrame, Pms7003Sensor};
-use rumqtt::{MqttClient, MqttOptions, QoS, ReconnectOptions};
 use serde_derive::Serialize;
 use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use structopt::StructOpt;
 use tokio::macros::support::Future;
@@ -11,9 +22,15 @@ use tokio::macros::support::Future;
 #[derive(StructOpt)]
 #[structopt(version = "1.0")]
 struct Opts {
+    /// Path to a TOML/JSON config file declaring sensors and metrics.
+    /// When given, it takes over sensor setup: --device, --interval and
+    /// --grouped are not used, and --measurements only sets the initial
+    /// measurement count.
+    #[structopt(short = "c", long = "config")]
+    config: Option<PathBuf>,
     /// Path to pms-7003 device
     #[structopt(short = "d", long = "device")]
-    device: PathBuf,
+    device: Option<PathBuf>,
     /// MQTT broker addreess
     #[structopt(short = "b", long = "broker")]
     broker: String,
@@ -29,88 +46,748 @@ struct Opts {
     /// Number of queries for a single measurement
     #[structopt(short = "m", long = "measurements", default_value = "10")]
     measurements: usize,
+    /// Publish one grouped JSON document per sampling cycle instead of a
+    /// separate retained topic per metric
+    #[structopt(short = "g", long = "grouped")]
+    grouped: bool,
+    /// MQTT protocol version to speak: "v4" (3.1.1, default) or "v5"
+    #[structopt(long = "mqtt-version", default_value = "v4")]
+    mqtt_version: mqtt::Version,
+    /// Connect to the broker over TLS
+    #[structopt(long = "tls")]
+    tls: bool,
+    /// CA certificate used to verify the broker, required with --tls
+    #[structopt(long = "ca-cert")]
+    ca_cert: Option<PathBuf>,
+    /// Client certificate for mutual TLS
+    #[structopt(long = "client-cert")]
+    client_cert: Option<PathBuf>,
+    /// Client private key for mutual TLS
+    #[structopt(long = "client-key")]
+    client_key: Option<PathBuf>,
+    /// Bypass the PMS7003/BME280 hardware and publish synthetic, slowly
+    /// drifting readings instead, for testing and dashboard development
+    #[structopt(long = "simulate")]
+    simulate: bool,
+    /// Amplitude of the simulated PM2.5 sawtooth wave, in ug/m^3
+    #[structopt(long = "sim-pm-amplitude", default_value = "50.0")]
+    sim_pm_amplitude: f64,
+    /// Period of the simulated PM sawtooth wave, e.g. "10m"
+    #[structopt(long = "sim-pm-period", default_value = "10m")]
+    sim_pm_period: String,
+    /// Amplitude of the simulated temperature sine wave, in degrees
+    #[structopt(long = "sim-temp-amplitude", default_value = "5.0")]
+    sim_temp_amplitude: f64,
+    /// Period of the simulated temperature sine wave, e.g. "30m"
+    #[structopt(long = "sim-temp-period", default_value = "30m")]
+    sim_temp_period: String,
 }
+
 #[tokio::main]
 async fn main() {
     let opts = Opts::from_args();
 
-    let mqtt_client = std::sync::Arc::new(std::sync::Mutex::new(mqtt_connection(&opts)));
+    let status_topic = format!("{}/status/availability", opts.topic);
+    let (mqtt_client, notifications) = mqtt::connect(mqtt::ConnectOptions {
+        client_id: "aqbc".to_string(),
+        broker: opts.broker.clone(),
+        port: opts.port,
+        version: opts.mqtt_version,
+        tls: tls_options(&opts),
+        last_will_topic: status_topic.clone(),
+    });
+    mqtt_client.subscribe(&format!("{}/cmd/#", opts.topic)).await;
     println!("MQTT client connected");
 
-    let device = linux_embedded_hal::Serial::open(&opts.device).unwrap();
+    mqtt_client.publish(&status_topic, true, "online").await;
+
+    tokio::spawn(publish_offline_on_shutdown(
+        mqtt_client.clone(),
+        status_topic,
+    ));
+
+    if opts.simulate {
+        run_simulated(opts, mqtt_client, notifications).await;
+        return;
+    }
+
+    match &opts.config {
+        Some(config_path) => {
+            let config = Config::load(config_path).unwrap_or_else(|e| panic!("{}", e));
+            run_configured(
+                mqtt_client,
+                opts.topic.clone(),
+                opts.measurements,
+                config,
+                notifications,
+            )
+            .await;
+        }
+        None => run_legacy(opts, mqtt_client, notifications).await,
+    }
+}
+
+/// Same sampling-cycle pipeline as [`run_legacy`], but fed by
+/// [`simulate::Simulator`] instead of real hardware.
+async fn run_simulated(
+    opts: Opts,
+    mqtt_client: MqttHandle,
+    notifications: tokio::sync::mpsc::UnboundedReceiver<mqtt::IncomingMessage>,
+) {
+    println!("Running in --simulate mode, no hardware will be used");
+
+    let controls = Arc::new(Mutex::new(Controls::new(
+        Duration::from_secs(opts.interval.into()),
+        opts.measurements,
+    )));
+    let sample_now = Arc::new(tokio::sync::Notify::new());
+
+    tokio::spawn(command_listener(
+        notifications,
+        opts.topic.clone(),
+        controls.clone(),
+        sample_now.clone(),
+    ));
+
+    let simulator = simulate::Simulator::new(
+        opts.sim_pm_amplitude,
+        config::parse_period(&opts.sim_pm_period).unwrap_or_else(|e| panic!("{}", e)),
+        opts.sim_temp_amplitude,
+        config::parse_period(&opts.sim_temp_period).unwrap_or_else(|e| panic!("{}", e)),
+    );
+
+    let mut current_interval = controls.lock().unwrap().interval;
+    let mut interval = tokio::time::interval(current_interval);
+
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {}
+            _ = sample_now.notified() => {}
+        }
+
+        {
+            let mut controls = controls.lock().unwrap();
+            if controls.interval != current_interval {
+                current_interval = controls.interval;
+                interval = tokio::time::interval(current_interval);
+            }
+            // A simulated sensor has no laser/fan to rest, but still
+            // acknowledges wake/sleep commands so remote control can be
+            // exercised end to end.
+            controls.wake = false;
+            controls.sleep = false;
+        }
+
+        let pm = simulator.pm_reading();
+        let environment = simulator.environment_reading();
+        let status = AitQualityStatus {
+            pm_1_0: pm.pm1_0,
+            pm_2_5: pm.pm2_5,
+            pm_10: pm.pm10,
+            timestamp: chrono::Local::now(),
+        };
+
+        if opts.grouped {
+            let aqi_value = aqi::from_pm(status.pm_2_5 as f64, status.pm_10 as f64);
+            let measurement = Measurement {
+                time: status.timestamp,
+                pm: Some(PmReadings {
+                    pm1_0: status.pm_1_0,
+                    pm2_5: status.pm_2_5,
+                    pm10: status.pm_10,
+                }),
+                environment: Some(EnvironmentReadings {
+                    temperature: environment.temperature,
+                    humidity: environment.humidity,
+                    pressure: environment.pressure,
+                }),
+                aqi: Some(AqiReading {
+                    value: aqi_value,
+                    category: aqi::category(aqi_value),
+                }),
+            };
+
+            mqtt_client
+                .publish(
+                    &format!("{}/measurement", opts.topic),
+                    true,
+                    serde_json::to_string(&measurement).unwrap(),
+                )
+                .await;
+            println!("Published: {:?}", measurement);
+        } else {
+            publish_status(&mqtt_client, &status, &opts.topic).await;
+            mqtt_client
+                .publish(
+                    &format!("{}/humidity", opts.topic),
+                    true,
+                    format!("{:.2}%", environment.humidity),
+                )
+                .await;
+            mqtt_client
+                .publish(
+                    &format!("{}/temperature", opts.topic),
+                    true,
+                    format!("{:.2}°", environment.temperature),
+                )
+                .await;
+            mqtt_client
+                .publish(
+                    &format!("{}/pressure", opts.topic),
+                    true,
+                    format!("{:.2}", environment.pressure),
+                )
+                .await;
+        }
+    }
+}
+
+fn tls_options(opts: &Opts) -> Option<mqtt::TlsOptions> {
+    if !opts.tls {
+        return None;
+    }
+
+    Some(mqtt::TlsOptions {
+        ca_cert: opts
+            .ca_cert
+            .clone()
+            .expect("--ca-cert is required with --tls"),
+        client_cert: opts.client_cert.clone(),
+        client_key: opts.client_key.clone(),
+    })
+}
+
+/// Drains the notification stream for `<topic>/cmd/#` publishes and
+/// applies them to the shared runtime controls, so the monitor can be
+/// reconfigured without a restart.
+async fn command_listener(
+    mut notifications: tokio::sync::mpsc::UnboundedReceiver<mqtt::IncomingMessage>,
+    topic: String,
+    controls: Arc<Mutex<Controls>>,
+    sample_now: Arc<tokio::sync::Notify>,
+) {
+    let prefix = format!("{}/cmd/", topic);
+    while let Some(message) = notifications.recv().await {
+        let suffix = match message.topic.strip_prefix(&prefix) {
+            Some(suffix) => suffix,
+            None => continue,
+        };
+
+        let payload = String::from_utf8_lossy(&message.payload);
+        let command = match commands::parse(suffix, &payload) {
+            Some(command) => command,
+            None => {
+                eprintln!("Ignoring unknown command '{}'", suffix);
+                continue;
+            }
+        };
+
+        let mut controls = controls.lock().unwrap();
+        match command {
+            Command::SetInterval(interval) => controls.interval = interval,
+            Command::SetMeasurements(measurements) => controls.measurements = measurements,
+            Command::Wake => controls.wake = true,
+            Command::Sleep => controls.sleep = true,
+            Command::Sample => sample_now.notify_one(),
+        }
+    }
+}
+
+/// Waits for Ctrl-C, publishes a clean `offline` status and exits, so
+/// graceful shutdowns don't have to wait for the broker to time out the
+/// last-will message.
+async fn publish_offline_on_shutdown(mqtt_client: MqttHandle, status_topic: String) {
+    let _ = tokio::signal::ctrl_c().await;
+    println!("Shutting down, publishing offline status");
+    mqtt_client.publish(&status_topic, true, "offline").await;
+    std::process::exit(0);
+}
+
+/// Original single-device behaviour, used when no `--config` file is
+/// given. The sampling interval and measurement count can still be
+/// changed at runtime via the `<topic>/cmd/#` topics.
+async fn run_legacy(
+    opts: Opts,
+    mqtt_client: MqttHandle,
+    notifications: tokio::sync::mpsc::UnboundedReceiver<mqtt::IncomingMessage>,
+) {
+    let device = opts
+        .device
+        .as_ref()
+        .expect("--device is required when --config is not given");
+
+    let device = linux_embedded_hal::Serial::open(device).unwrap();
     let mut sensor = pms_7003::Pms7003Sensor::new(device);
     println!("Pms7003 connected");
 
     let _ = sensor.active();
 
-    let mut interval = tokio::time::interval(Duration::from_secs(opts.interval.into()));
+    let controls = Arc::new(Mutex::new(Controls::new(
+        Duration::from_secs(opts.interval.into()),
+        opts.measurements,
+    )));
+    let sample_now = Arc::new(tokio::sync::Notify::new());
+
+    tokio::spawn(command_listener(
+        notifications,
+        opts.topic.clone(),
+        controls.clone(),
+        sample_now.clone(),
+    ));
+
+    let mut current_interval = controls.lock().unwrap().interval;
+    let mut interval = tokio::time::interval(current_interval);
 
     let i2c_bus = linux_embedded_hal::I2cdev::new("/dev/i2c-1").unwrap();
     let mut bme280 = bme280::BME280::new_primary(i2c_bus, linux_embedded_hal::Delay);
     bme280.init().unwrap();
     println!("Bme280 connected");
 
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {}
+            _ = sample_now.notified() => {}
+        }
+
+        let num_of_measurements = {
+            let mut controls = controls.lock().unwrap();
+
+            if controls.interval != current_interval {
+                current_interval = controls.interval;
+                interval = tokio::time::interval(current_interval);
+            }
+            if controls.wake {
+                println!("Waking sensor on command");
+                let _ = sensor.wake();
+                controls.wake = false;
+            }
+            if controls.sleep {
+                println!("Putting sensor to sleep on command");
+                let _ = sensor.sleep();
+                controls.sleep = false;
+            }
+
+            controls.measurements
+        };
+
+        if opts.grouped {
+            let measurement = Arc::new(Mutex::new(Measurement::new()));
+
+            let pollution_task = tokio::spawn(pollution_task_grouped(
+                sensor,
+                num_of_measurements,
+                measurement.clone(),
+            ));
+            let temperature_task =
+                tokio::spawn(temperature_task_grouped(bme280, measurement.clone()));
+
+            sensor = pollution_task.await.unwrap();
+            bme280 = temperature_task.await.unwrap();
+
+            let measurement = Arc::try_unwrap(measurement)
+                .expect("no other references to the measurement remain")
+                .into_inner()
+                .unwrap();
+            mqtt_client
+                .publish(
+                    &format!("{}/measurement", opts.topic),
+                    true,
+                    serde_json::to_string(&measurement).unwrap(),
+                )
+                .await;
+            println!("Published: {:?}", measurement);
+        } else {
+            let pollution_task = tokio::spawn(pollution_task(
+                sensor,
+                num_of_measurements,
+                mqtt_client.clone(),
+                opts.topic.clone(),
+            ));
+
+            let temperature_task = tokio::spawn(temperature_task(
+                bme280,
+                mqtt_client.clone(),
+                opts.topic.clone(),
+            ));
+
+            sensor = pollution_task.await.unwrap();
+            bme280 = temperature_task.await.unwrap();
+        }
+    }
+}
+
+/// Config-driven behaviour: each physical sensor is read by a single
+/// dedicated reader task into a cached reading, and each declared metric
+/// gets its own lightweight Tokio interval that just publishes the latest
+/// cached value — so e.g. PM readings and temperature can be published at
+/// different rates without each metric re-triggering its own full sensor
+/// read cycle. Each reader polls at a rate derived from its own sensor's
+/// metrics, not one rate shared across the whole config; a `SetInterval`
+/// command scales every reader by the same factor instead of forcing them
+/// all to one absolute rate.
+async fn run_configured(
+    mqtt_client: MqttHandle,
+    base_topic: String,
+    num_of_measurements: usize,
+    config: Config,
+    notifications: tokio::sync::mpsc::UnboundedReceiver<mqtt::IncomingMessage>,
+) {
+    let reference_period = config
+        .sensors
+        .iter()
+        .flat_map(|sensor| match sensor {
+            SensorConfig::Pms7003 { metrics, .. } => metrics.iter(),
+            SensorConfig::Bme280 { metrics, .. } => metrics.iter(),
+        })
+        .map(|metric| metric.period)
+        .min()
+        .unwrap_or_else(|| Duration::from_secs(60));
+
+    let controls = Arc::new(Mutex::new(Controls::new(reference_period, num_of_measurements)));
+    let sample_now = Arc::new(tokio::sync::Notify::new());
+
+    tokio::spawn(command_listener(
+        notifications,
+        base_topic.clone(),
+        controls.clone(),
+        sample_now.clone(),
+    ));
+
+    let mut tasks = Vec::new();
+
+    for sensor in config.sensors {
+        match sensor {
+            SensorConfig::Pms7003 { device, metrics } => {
+                let own_default_period = metrics
+                    .iter()
+                    .map(|metric| metric.period)
+                    .min()
+                    .unwrap_or(reference_period);
+
+                let serial = linux_embedded_hal::Serial::open(&device).unwrap();
+                let mut sensor = Pms7003Sensor::new(serial);
+                let _ = sensor.active();
+                let sensor = Arc::new(Mutex::new(sensor));
+                println!("Pms7003 connected ({})", device.display());
+
+                let cache = Arc::new(Mutex::new(None));
+
+                tasks.push(tokio::spawn(pms7003_reader_task(
+                    sensor,
+                    controls.clone(),
+                    reference_period,
+                    own_default_period,
+                    sample_now.clone(),
+                    cache.clone(),
+                )));
+
+                for metric in metrics {
+                    if !PM_METRICS.contains(&metric.name.as_str()) {
+                        eprintln!("Unknown pms7003 metric '{}'", metric.name);
+                        continue;
+                    }
+                    tasks.push(tokio::spawn(cached_metric_task(
+                        cache.clone(),
+                        mqtt_client.clone(),
+                        base_topic.clone(),
+                        metric,
+                        pm_field,
+                    )));
+                }
+            }
+            SensorConfig::Bme280 { bus, address, metrics } => {
+                let own_default_period = metrics
+                    .iter()
+                    .map(|metric| metric.period)
+                    .min()
+                    .unwrap_or(reference_period);
+
+                let i2c_bus = linux_embedded_hal::I2cdev::new(&bus).unwrap();
+                let mut bme280 = match address {
+                    Bme280Address::Primary => {
+                        bme280::BME280::new_primary(i2c_bus, linux_embedded_hal::Delay)
+                    }
+                    Bme280Address::Secondary => {
+                        bme280::BME280::new_secondary(i2c_bus, linux_embedded_hal::Delay)
+                    }
+                };
+                bme280.init().unwrap();
+                println!("Bme280 connected ({})", bus.display());
+                let bme280 = Arc::new(Mutex::new(bme280));
+
+                let cache = Arc::new(Mutex::new(None));
+
+                tasks.push(tokio::spawn(bme280_reader_task(
+                    bme280,
+                    controls.clone(),
+                    reference_period,
+                    own_default_period,
+                    sample_now.clone(),
+                    cache.clone(),
+                )));
+
+                for metric in metrics {
+                    if !ENVIRONMENT_METRICS.contains(&metric.name.as_str()) {
+                        eprintln!("Unknown bme280 metric '{}'", metric.name);
+                        continue;
+                    }
+                    tasks.push(tokio::spawn(cached_metric_task(
+                        cache.clone(),
+                        mqtt_client.clone(),
+                        base_topic.clone(),
+                        metric,
+                        environment_field,
+                    )));
+                }
+            }
+        }
+    }
+
+    for task in tasks {
+        let _ = task.await;
+    }
+}
+
+/// Runs the wake/warm-up/read/sleep cycle on one physical PMS7003 and
+/// caches the latest reading, instead of running it once per declared
+/// metric. Polls at `own_default_period`, scaled by however far a
+/// `SetInterval` command has moved `controls.interval` away from
+/// `reference_period` — so one command speeds up or slows down every
+/// sensor in the config by the same factor instead of forcing them all to
+/// the same absolute rate. The measurement count is shared with every other
+/// sensor in this config via `controls`; each wake/sleep command is taken
+/// by whichever PMS7003 reader runs next, so only one sensor acts on it.
+async fn pms7003_reader_task(
+    sensor: Arc<Mutex<Pms7003>>,
+    controls: Arc<Mutex<Controls>>,
+    reference_period: Duration,
+    own_default_period: Duration,
+    sample_now: Arc<tokio::sync::Notify>,
+    cache: Arc<Mutex<Option<PmReadings>>>,
+) {
+    let mut last_seen_interval = controls.lock().unwrap().interval;
+    let mut interval =
+        tokio::time::interval(scaled_interval(own_default_period, last_seen_interval, reference_period));
+
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {}
+            _ = sample_now.notified() => {}
+        }
+
+        let (num_of_measurements, wake, sleep) = {
+            let mut controls = controls.lock().unwrap();
+            if controls.interval != last_seen_interval {
+                last_seen_interval = controls.interval;
+                interval = tokio::time::interval(scaled_interval(
+                    own_default_period,
+                    last_seen_interval,
+                    reference_period,
+                ));
+            }
+            let wake = std::mem::take(&mut controls.wake);
+            let sleep = std::mem::take(&mut controls.sleep);
+            (controls.measurements, wake, sleep)
+        };
+
+        let sensor = sensor.clone();
+        let status = tokio::task::spawn_blocking(move || {
+            let mut sensor = sensor.lock().unwrap();
+            if wake {
+                println!("Waking sensor on command");
+                let _ = sensor.wake();
+            }
+            if sleep {
+                println!("Putting sensor to sleep on command");
+                let _ = sensor.sleep();
+            }
+            get_air_quality_status(&mut sensor, num_of_measurements)
+        })
+        .await
+        .unwrap();
+
+        match status {
+            Ok(status) => {
+                *cache.lock().unwrap() = Some(PmReadings {
+                    pm1_0: status.pm_1_0,
+                    pm2_5: status.pm_2_5,
+                    pm10: status.pm_10,
+                });
+            }
+            Err(e) => eprintln!("Failed to read pms7003: {:?}", e),
+        }
+    }
+}
+
+/// Reads one physical BME280 on its own schedule and caches the latest
+/// reading, instead of measuring once per declared metric. Polls at
+/// `own_default_period`, scaled the same way as [`pms7003_reader_task`],
+/// and honors the forced-sample command; a BME280 has no measurement count
+/// or wake/sleep state, so those fields of `controls` don't apply here.
+async fn bme280_reader_task(
+    bme280: Arc<Mutex<Bme>>,
+    controls: Arc<Mutex<Controls>>,
+    reference_period: Duration,
+    own_default_period: Duration,
+    sample_now: Arc<tokio::sync::Notify>,
+    cache: Arc<Mutex<Option<EnvironmentReadings>>>,
+) {
+    let mut last_seen_interval = controls.lock().unwrap().interval;
+    let mut interval =
+        tokio::time::interval(scaled_interval(own_default_period, last_seen_interval, reference_period));
+
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {}
+            _ = sample_now.notified() => {}
+        }
+
+        {
+            let mut controls = controls.lock().unwrap();
+            if controls.interval != last_seen_interval {
+                last_seen_interval = controls.interval;
+                interval = tokio::time::interval(scaled_interval(
+                    own_default_period,
+                    last_seen_interval,
+                    reference_period,
+                ));
+            }
+        }
+
+        let bme280 = bme280.clone();
+        let measurement = tokio::task::spawn_blocking(move || bme280.lock().unwrap().measure())
+            .await
+            .unwrap();
+
+        match measurement {
+            Ok(measurement) => {
+                *cache.lock().unwrap() = Some(EnvironmentReadings {
+                    temperature: measurement.temperature,
+                    humidity: measurement.humidity,
+                    pressure: measurement.pressure,
+                });
+            }
+            Err(_) => eprintln!("Failed to read bme280"),
+        }
+    }
+}
+
+/// Scales a sensor's own metric-derived default period by however far the
+/// shared `controls_interval` has moved away from `reference_period`, so a
+/// single `SetInterval` command speeds up or slows down every sensor in a
+/// config by the same factor rather than forcing them all to one absolute
+/// rate.
+fn scaled_interval(own_default_period: Duration, controls_interval: Duration, reference_period: Duration) -> Duration {
+    let scale = controls_interval.as_secs_f64() / reference_period.as_secs_f64();
+    own_default_period.mul_f64(scale)
+}
+
+const PM_METRICS: &[&str] = &["pm1_0", "pm2_5", "pm10"];
+const ENVIRONMENT_METRICS: &[&str] = &["temperature", "humidity", "pressure"];
+
+fn pm_field(reading: &PmReadings, name: &str) -> Option<f64> {
+    match name {
+        "pm1_0" => Some(reading.pm1_0 as f64),
+        "pm2_5" => Some(reading.pm2_5 as f64),
+        "pm10" => Some(reading.pm10 as f64),
+        _ => None,
+    }
+}
+
+fn environment_field(reading: &EnvironmentReadings, name: &str) -> Option<f64> {
+    match name {
+        "temperature" => Some(reading.temperature as f64),
+        "humidity" => Some(reading.humidity as f64),
+        "pressure" => Some(reading.pressure as f64),
+        _ => None,
+    }
+}
+
+/// Publishes one declared metric on its own schedule, reading the latest
+/// value out of a reader task's cache rather than touching the hardware.
+async fn cached_metric_task<T>(
+    cache: Arc<Mutex<Option<T>>>,
+    mqtt_client: MqttHandle,
+    base_topic: String,
+    metric: MetricConfig,
+    field: fn(&T, &str) -> Option<f64>,
+) {
+    let mut interval = tokio::time::interval(metric.period);
     loop {
         interval.tick().await;
 
-        let pollution_task = tokio::spawn(pollution_task(
-            sensor,
-            opts.measurements,
-            mqtt_client.clone(),
-            opts.topic.clone(),
-        ));
+        let value = {
+            let reading = cache.lock().unwrap();
+            reading.as_ref().and_then(|r| field(r, &metric.name))
+        };
 
-        let temperature_task = tokio::spawn(temperature_task(
-            bme280,
-            mqtt_client.clone(),
-            opts.topic.clone(),
-        ));
+        let value = match value {
+            Some(value) => value,
+            None => continue,
+        };
 
-        sensor = pollution_task.await.unwrap();
-        bme280 = temperature_task.await.unwrap();
+        publish_metric(&mqtt_client, &base_topic, &metric, value).await;
     }
 }
 
+async fn publish_metric(mqtt_client: &MqttHandle, base_topic: &str, metric: &MetricConfig, value: f64) {
+    let topic = match &metric.topic {
+        Some(suffix) => format!("{}/{}", base_topic, suffix),
+        None => format!("{}/{}", base_topic, metric.name),
+    };
+    let scaled = value * metric.scale;
+
+    mqtt_client
+        .publish(&topic, true, format!("{:.2}", scaled))
+        .await;
+    println!("Published: {} = {:.2}", topic, scaled);
+}
+
 type Pms7003 = Pms7003Sensor<Serial>;
 type Bme = bme280::BME280<linux_embedded_hal::I2cdev, linux_embedded_hal::Delay>;
 
 fn pollution_task(
     mut sensor: Pms7003,
     num_of_measurements: usize,
-    mqtt_client: std::sync::Arc<std::sync::Mutex<MqttClient>>,
+    mqtt_client: MqttHandle,
     topic: String,
 ) -> impl Future<Output = Pms7003> {
     async move {
         let status = get_air_quality_status(&mut sensor, num_of_measurements).unwrap();
-        publish_status(&mut mqtt_client.lock().unwrap(), &status, &topic);
+        publish_status(&mqtt_client, &status, &topic).await;
         sensor
     }
 }
 
 fn temperature_task(
     mut bme280: Bme,
-    mqtt_client: std::sync::Arc<std::sync::Mutex<MqttClient>>,
+    mqtt_client: MqttHandle,
     topic: String,
 ) -> impl Future<Output = Bme> {
     async move {
         let measurement = bme280.measure().unwrap();
 
-        let mut client_lock = mqtt_client.lock().unwrap();
-        publish(
-            &mut client_lock,
-            &format!("{}/humidity", topic),
-            &format!("{:.2}%", measurement.humidity),
-        );
-        publish(
-            &mut client_lock,
-            &format!("{}/temperature", topic),
-            &format!("{:.2}°", measurement.temperature),
-        );
-        publish(
-            &mut client_lock,
-            &format!("{}/pressure", topic),
-            &format!("{:.2}", measurement.pressure),
-        );
+        mqtt_client
+            .publish(
+                &format!("{}/humidity", topic),
+                true,
+                format!("{:.2}%", measurement.humidity),
+            )
+            .await;
+        mqtt_client
+            .publish(
+                &format!("{}/temperature", topic),
+                true,
+                format!("{:.2}°", measurement.temperature),
+            )
+            .await;
+        mqtt_client
+            .publish(
+                &format!("{}/pressure", topic),
+                true,
+                format!("{:.2}", measurement.pressure),
+            )
+            .await;
 
         println!(
             "Published: humidity: {} pressure: {} temperature: {}",
@@ -121,6 +798,45 @@ fn temperature_task(
     }
 }
 
+fn pollution_task_grouped(
+    mut sensor: Pms7003,
+    num_of_measurements: usize,
+    measurement: Arc<Mutex<Measurement>>,
+) -> impl Future<Output = Pms7003> {
+    async move {
+        let status = get_air_quality_status(&mut sensor, num_of_measurements).unwrap();
+        let aqi_value = aqi::from_pm(status.pm_2_5 as f64, status.pm_10 as f64);
+
+        let mut measurement = measurement.lock().unwrap();
+        measurement.pm = Some(PmReadings {
+            pm1_0: status.pm_1_0,
+            pm2_5: status.pm_2_5,
+            pm10: status.pm_10,
+        });
+        measurement.aqi = Some(AqiReading {
+            value: aqi_value,
+            category: aqi::category(aqi_value),
+        });
+        drop(measurement);
+        sensor
+    }
+}
+
+fn temperature_task_grouped(
+    mut bme280: Bme,
+    measurement: Arc<Mutex<Measurement>>,
+) -> impl Future<Output = Bme> {
+    async move {
+        let reading = bme280.measure().unwrap();
+        measurement.lock().unwrap().environment = Some(EnvironmentReadings {
+            temperature: reading.temperature,
+            humidity: reading.humidity,
+            pressure: reading.pressure,
+        });
+        bme280
+    }
+}
+
 #[derive(Debug, Serialize)]
 struct AitQualityStatus {
     pm_1_0: u32,
@@ -169,35 +885,47 @@ fn get_air_quality_status(
     Ok(status_from_measurements(&measurements))
 }
 
-fn publish_status(mqtt_client: &mut MqttClient, status: &AitQualityStatus, topic: &str) {
-    publish(
-        mqtt_client,
-        &format!("{}/status", topic),
-        &serde_json::to_string_pretty(status).unwrap(),
-    );
-    publish(
-        mqtt_client,
-        &format!("{}/pm10", topic),
-        &format!("{} ug/m^3", status.pm_10),
-    );
-    publish(
-        mqtt_client,
-        &format!("{}/pm1_0", topic),
-        &format!("{} ug/m^3", status.pm_1_0),
-    );
-    publish(
-        mqtt_client,
-        &format!("{}/pm2_5", topic),
-        &format!("{} ug/m^3", status.pm_2_5),
-    );
+async fn publish_status(mqtt_client: &MqttHandle, status: &AitQualityStatus, topic: &str) {
+    mqtt_client
+        .publish(
+            &format!("{}/status", topic),
+            true,
+            serde_json::to_string_pretty(status).unwrap(),
+        )
+        .await;
+    mqtt_client
+        .publish(
+            &format!("{}/pm10", topic),
+            true,
+            format!("{} ug/m^3", status.pm_10),
+        )
+        .await;
+    mqtt_client
+        .publish(
+            &format!("{}/pm1_0", topic),
+            true,
+            format!("{} ug/m^3", status.pm_1_0),
+        )
+        .await;
+    mqtt_client
+        .publish(
+            &format!("{}/pm2_5", topic),
+            true,
+            format!("{} ug/m^3", status.pm_2_5),
+        )
+        .await;
 
-    println!("Published: {:?}", status);
-}
+    let aqi_value = aqi::from_pm(status.pm_2_5 as f64, status.pm_10 as f64);
+    mqtt_client
+        .publish(
+            &format!("{}/aqi", topic),
+            true,
+            serde_json::json!({ "aqi": aqi_value, "category": aqi::category(aqi_value) })
+                .to_string(),
+        )
+        .await;
 
-fn publish(client: &mut MqttClient, topic: &str, payload: &str) {
-    client
-        .publish(topic, QoS::AtLeastOnce, true, payload)
-        .unwrap();
+    println!("Published: {:?}", status);
 }
 
 fn status_from_measurements(measurements: &[OutputFrame]) -> AitQualityStatus {
@@ -210,16 +938,3 @@ fn status_from_measurements(measurements: &[OutputFrame]) -> AitQualityStatus {
         timestamp: chrono::Local::now(),
     }
 }
-
-fn mqtt_connection(opts: &Opts) -> MqttClient {
-    let reconnection_options = ReconnectOptions::Always(10);
-    let mqtt_options = MqttOptions::new("aqbc", &opts.broker, opts.port)
-        .set_keep_alive(10)
-        .set_inflight(3)
-        .set_request_channel_capacity(3)
-        .set_reconnect_opts(reconnection_options)
-        .set_clean_session(false);
-
-    let (mqtt_client, _notifications) = MqttClient::start(mqtt_options).unwrap();
-    mqtt_client
-}
@@ -0,0 +1,50 @@
+use chrono::{DateTime, Local};
+use serde_derive::Serialize;
+
+/// One full sampling cycle grouped into a single timestamped record, for
+/// publishing as one JSON document instead of many individual scalar
+/// topics.
+#[derive(Debug, Serialize)]
+pub struct Measurement {
+    pub time: DateTime<Local>,
+    pub pm: Option<PmReadings>,
+    pub environment: Option<EnvironmentReadings>,
+    pub aqi: Option<AqiReading>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PmReadings {
+    pub pm1_0: u32,
+    pub pm2_5: u32,
+    pub pm10: u32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AqiReading {
+    pub value: u32,
+    pub category: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EnvironmentReadings {
+    pub temperature: f32,
+    pub humidity: f32,
+    pub pressure: f32,
+}
+
+impl Measurement {
+    pub fn new() -> Self {
+        Measurement {
+            time: Local::now(),
+            pm: None,
+            environment: None,
+            aqi: None,
+        }
+    }
+}
+
+impl Default for Measurement {
+    fn default() -> Self {
+        Self::new()
+    }
+}
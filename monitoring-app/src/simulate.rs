@@ -0,0 +1,136 @@
+use std::f64::consts::PI;
+use std::time::{Duration, Instant};
+
+/// A slowly drifting, deterministic waveform used to fake sensor readings
+/// when running with `--simulate`, so the MQTT topics, AQI and dashboards
+/// can be exercised without any hardware attached.
+#[derive(Debug, Clone, Copy)]
+pub struct Waveform {
+    pub shape: Shape,
+    pub offset: f64,
+    pub amplitude: f64,
+    pub period: Duration,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Shape {
+    Sawtooth,
+    Sine,
+}
+
+impl Waveform {
+    pub fn value_at(&self, start: Instant, now: Instant) -> f64 {
+        let phase = (now - start).as_secs_f64() / self.period.as_secs_f64();
+
+        let unit = match self.shape {
+            // Ramps linearly from -1 to 1 over one period.
+            Shape::Sawtooth => 2.0 * (phase - phase.floor()) - 1.0,
+            Shape::Sine => (phase * 2.0 * PI).sin(),
+        };
+
+        self.offset + self.amplitude * unit
+    }
+}
+
+/// Generates synthetic PM1.0/PM2.5/PM10 and temperature/humidity/pressure
+/// readings in place of the PMS7003 and BME280, feeding the same
+/// publishing pipeline as real hardware.
+pub struct Simulator {
+    start: Instant,
+    pm2_5: Waveform,
+    pm10: Waveform,
+    temperature: Waveform,
+}
+
+pub struct PmReading {
+    pub pm1_0: u32,
+    pub pm2_5: u32,
+    pub pm10: u32,
+}
+
+pub struct EnvironmentReading {
+    pub temperature: f32,
+    pub humidity: f32,
+    pub pressure: f32,
+}
+
+impl Simulator {
+    pub fn new(pm_amplitude: f64, pm_period: Duration, temp_amplitude: f64, temp_period: Duration) -> Self {
+        Simulator {
+            start: Instant::now(),
+            pm2_5: Waveform {
+                shape: Shape::Sawtooth,
+                offset: pm_amplitude,
+                amplitude: pm_amplitude,
+                period: pm_period,
+            },
+            pm10: Waveform {
+                shape: Shape::Sawtooth,
+                offset: pm_amplitude * 1.5,
+                amplitude: pm_amplitude * 1.5,
+                period: pm_period,
+            },
+            temperature: Waveform {
+                shape: Shape::Sine,
+                offset: 20.0,
+                amplitude: temp_amplitude,
+                period: temp_period,
+            },
+        }
+    }
+
+    pub fn pm_reading(&self) -> PmReading {
+        let now = Instant::now();
+        let pm2_5 = self.pm2_5.value_at(self.start, now).max(0.0) as u32;
+        let pm10 = self.pm10.value_at(self.start, now).max(0.0) as u32;
+
+        PmReading {
+            pm1_0: pm2_5 * 7 / 10,
+            pm2_5,
+            pm10,
+        }
+    }
+
+    pub fn environment_reading(&self) -> EnvironmentReading {
+        let temperature = self.temperature.value_at(self.start, Instant::now()) as f32;
+
+        EnvironmentReading {
+            temperature,
+            humidity: 45.0,
+            pressure: 1013.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sawtooth_ramps_between_bounds() {
+        let waveform = Waveform {
+            shape: Shape::Sawtooth,
+            offset: 0.0,
+            amplitude: 10.0,
+            period: Duration::from_secs(100),
+        };
+        let start = Instant::now();
+
+        assert_eq!(waveform.value_at(start, start), -10.0);
+        assert_eq!(waveform.value_at(start, start + Duration::from_secs(50)), 0.0);
+        assert_eq!(waveform.value_at(start, start + Duration::from_secs(75)), 5.0);
+    }
+
+    #[test]
+    fn sine_starts_at_offset() {
+        let waveform = Waveform {
+            shape: Shape::Sine,
+            offset: 20.0,
+            amplitude: 5.0,
+            period: Duration::from_secs(100),
+        };
+        let start = Instant::now();
+
+        assert!((waveform.value_at(start, start) - 20.0).abs() < 1e-9);
+    }
+}